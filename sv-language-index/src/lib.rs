@@ -10,14 +10,57 @@ use position::{DocumentPosition, DocumentRange, LineIndex, Position, Range};
 use sv_parser::*;
 
 pub mod position;
+pub mod revision;
 pub mod semantic;
+pub mod symbol_index;
 type ItemId = generational_arena::Index;
 
+use revision::Revision;
+use symbol_index::FileSymbolIndex;
+
+/// Builds a `global_items` key that namespaces a declaration by its kind
+/// (`"module"`, `"interface"`, `"program"`, `"package"`) so a module and an
+/// interface of the same name never collide. `scope` is the dot-separated
+/// path of enclosing module names for a declaration nested inside another
+/// one (empty for a top-level declaration), giving nested declarations their
+/// own scope instead of flattening them into the global one.
+fn namespace_key(namespace: &str, scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        format!("{namespace}::{name}")
+    } else {
+        format!("{namespace}::{scope}.{name}")
+    }
+}
+
 pub struct DataPerFile {
     pub line_index: LineIndex,
     items: Arena<semantic::Item>,
     location_map: Vec<(Range, ItemId)>,
     global_items: HashMap<String, ItemId>,
+    symbol_index: FileSymbolIndex,
+    /// Reverse index: module name -> every `ModuleIdentifier` item in this
+    /// file that refers to (instantiates) that module.
+    references: HashMap<String, Vec<ItemId>>,
+    /// Port declarations of each module declared in this file, keyed by the
+    /// declaring `ModuleIdentifier`'s `ItemId` then by port name.
+    module_ports: HashMap<ItemId, HashMap<String, ItemId>>,
+    /// Parameter declarations of each module declared in this file, keyed
+    /// the same way as `module_ports`.
+    module_parameters: HashMap<ItemId, HashMap<String, ItemId>>,
+    fingerprint: u64,
+    /// Revision at which this data was last confirmed still up to date,
+    /// whether or not anything actually changed.
+    ///
+    /// This and `changed_at` are exposed read-only (see
+    /// [`DataPerFile::verified_at`]/[`DataPerFile::changed_at`]) for callers
+    /// that want to tell a no-op reparse from a real one; `Db` itself only
+    /// ever writes them. The fingerprint gate they record is a single
+    /// all-or-nothing check over the whole file, not the per-query
+    /// incremental caching the name might suggest — there's no per-query
+    /// memoization layer here yet that reads these back to skip work.
+    verified_at: Revision,
+    /// Revision at which this data was last actually recomputed.
+    changed_at: Revision,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -28,6 +71,8 @@ pub struct Db {
     files: BiHashMap<PathBuf, FileId>,
     data: HashMap<FileId, DataPerFile>,
     global_items: HashMap<String, (FileId, ItemId)>,
+    references: HashMap<String, Vec<(FileId, ItemId)>>,
+    revision: Revision,
 }
 
 impl Db {
@@ -46,18 +91,53 @@ impl Db {
     }
 
     pub fn update(&mut self, path: PathBuf, syntax_tree: &SyntaxTree) -> FileId {
-        let data = DataPerFile::new(syntax_tree);
+        self.revision = self.revision.next();
+        let revision = self.revision;
         let file_id = self.path_to_fileid(path);
+
+        let fingerprint = revision::fingerprint(syntax_tree);
+        if let Some(existing) = self.data.get_mut(&file_id) {
+            if existing.fingerprint == fingerprint {
+                // The reparsed tree is byte-for-byte identical to what we
+                // already have — i.e. the exact same text was resubmitted
+                // with no edit at all. The fingerprint hashes every node's
+                // `Locate` offset, so even a single inserted space shifts
+                // everything after it and busts this; it does not catch
+                // formatting-only or other semantically-equivalent edits.
+                // Still, nothing downstream needs to be recomputed here, so
+                // just record that it's still fresh.
+                existing.verified_at = revision;
+                return file_id;
+            }
+        }
+
+        let mut data = DataPerFile::new(syntax_tree);
+        data.fingerprint = fingerprint;
+        data.changed_at = revision;
+        data.verified_at = revision;
+
         if let Some(old_data) = self.data.insert(file_id, data) {
             for old_module in old_data.global_items.keys() {
                 self.global_items.remove(old_module);
             }
+            for old_module in old_data.references.keys() {
+                if let Some(sites) = self.references.get_mut(old_module) {
+                    sites.retain(|(site_file, _)| *site_file != file_id);
+                    if sites.is_empty() {
+                        self.references.remove(old_module);
+                    }
+                }
+            }
         }
         let new_data = unsafe { self.data.get(&file_id).unwrap_unchecked() };
         for (new_module, idx) in &new_data.global_items {
             self.global_items
                 .insert(new_module.clone(), (file_id, *idx));
         }
+        for (referenced_module, item_ids) in &new_data.references {
+            let sites = self.references.entry(referenced_module.clone()).or_default();
+            sites.extend(item_ids.iter().map(|item_id| (file_id, *item_id)));
+        }
 
         file_id
     }
@@ -66,42 +146,252 @@ impl Db {
         self.data.get(&file_id)
     }
 
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
     fn get_item_on_location(
         &self,
         file_id: FileId,
         position: &Position,
-    ) -> Option<&semantic::Item> {
+    ) -> Option<(ItemId, &semantic::Item)> {
         self.data.get(&file_id).and_then(|data| {
             let id = data
                 .location_map
                 .binary_search_by(|(loc, _)| loc.partial_cmp(&position).unwrap());
             id.ok().map(|id| {
-                data.items
-                    .get(data.location_map.get(id).unwrap().1)
-                    .unwrap()
+                let item_id = data.location_map.get(id).unwrap().1;
+                (item_id, data.items.get(item_id).unwrap())
             })
         })
     }
 
-    fn get_module(&self, module_name: &str) -> Option<(FileId, &semantic::Item)> {
-        let (file_id, item_id) = self.global_items.get(module_name)?;
+    /// Converts an incoming LSP position (UTF-16 columns) into the
+    /// byte-column `Position` every stored `Range` uses, so it can be
+    /// looked up in `location_map`.
+    fn to_byte_position(&self, file_id: FileId, position: &Position) -> Position {
+        self.data
+            .get(&file_id)
+            .map(|data| data.line_index.from_utf16(position.clone()))
+            .unwrap_or_else(|| position.clone())
+    }
+
+    /// Converts a stored byte-column `Range` into the UTF-16 columns LSP
+    /// clients expect, before it leaves the crate as a `DocumentRange`.
+    fn to_utf16_range(&self, file_id: FileId, range: &Range) -> Range {
+        let Some(data) = self.data.get(&file_id) else {
+            return range.clone();
+        };
+        Range {
+            begin: data.line_index.to_utf16(range.begin.clone()),
+            end: data.line_index.to_utf16(range.end.clone()),
+        }
+    }
+
+    /// Looks up a global item namespaced by kind (`"module"`, `"interface"`,
+    /// `"program"` or `"package"`) and name, exactly under `scope` — see
+    /// [`namespace_key`].
+    fn get_namespaced_item(
+        &self,
+        namespace: &str,
+        scope: &str,
+        name: &str,
+    ) -> Option<(FileId, ItemId, &semantic::Item)> {
+        let key = namespace_key(namespace, scope, name);
+        let (file_id, item_id) = self.global_items.get(&key)?;
         let data = unsafe { self.data.get(file_id).unwrap_unchecked() };
-        data.items.get(*item_id).map(|item| (*file_id, item))
+        data.items
+            .get(*item_id)
+            .map(|item| (*file_id, *item_id, item))
+    }
+
+    /// Like [`Db::get_namespaced_item`], but when `scope` itself doesn't
+    /// have a matching item, retries under each enclosing scope in turn
+    /// (`a.b.c` → `a.b` → `a` → top-level), so a nested declaration stays
+    /// reachable from anywhere within the module it's nested in rather than
+    /// only by its exact full scope.
+    fn resolve_namespaced_item(
+        &self,
+        namespace: &str,
+        scope: &str,
+        name: &str,
+    ) -> Option<(FileId, ItemId, &semantic::Item)> {
+        let mut scope = scope;
+        loop {
+            if let Some(found) = self.get_namespaced_item(namespace, scope, name) {
+                return Some(found);
+            }
+            if scope.is_empty() {
+                return None;
+            }
+            scope = scope.rsplit_once('.').map_or("", |(parent, _)| parent);
+        }
+    }
+
+    fn get_module(&self, scope: &str, module_name: &str) -> Option<(FileId, ItemId, &semantic::Item)> {
+        self.resolve_namespaced_item("module", scope, module_name)
+    }
+
+    /// Resolves a named connection (e.g. `.clk` or `.WIDTH` in an
+    /// instantiation) to the matching port or parameter declaration in the
+    /// instantiated module, depending on which of the owning instance's
+    /// connection lists `item_id` came from.
+    fn resolve_named_connection(
+        &self,
+        file_id: FileId,
+        item_id: ItemId,
+        name: &str,
+    ) -> Option<(FileId, &Range)> {
+        let data = self.data.get(&file_id)?;
+        let owning_instance = data.find_owning_instance(item_id)?;
+        let semantic::Item::ModuleInstance {
+            module_name,
+            ports,
+            parameters,
+            ..
+        } = owning_instance
+        else {
+            return None;
+        };
+        let semantic::Item::ModuleIdentifier {
+            module_name: target_module_name,
+            scope: target_scope,
+            ..
+        } = data.items.get(*module_name)?
+        else {
+            return None;
+        };
+        let (target_file_id, target_module_id, _) =
+            self.get_module(target_scope, target_module_name)?;
+        let target_data = self.data.get(&target_file_id)?;
+        let declarations = if ports.contains(&item_id) {
+            &target_data.module_ports
+        } else if parameters.contains(&item_id) {
+            &target_data.module_parameters
+        } else {
+            return None;
+        };
+        let declaration_id = declarations.get(&target_module_id)?.get(name)?;
+        let declaration = target_data.items.get(*declaration_id)?;
+
+        Some((target_file_id, declaration.location()))
+    }
+
+    /// Fuzzy-searches every file's symbol index for `query` (an LSP
+    /// `workspace/symbol` style lookup) and returns matches ranked best
+    /// first. Each file keeps its own sorted symbol slice rather than one
+    /// global structure; the matches from every file are collected and
+    /// sorted together by score.
+    pub fn find_symbols(&self, query: &str) -> Vec<DocumentRange> {
+        let mut matches: Vec<(i32, FileId, ItemId)> = Vec::new();
+        for (file_id, data) in &self.data {
+            for entry in data.symbol_index.entries() {
+                if let Some(score) = symbol_index::fuzzy_match(query, &entry.name) {
+                    matches.push((score, *file_id, entry.item));
+                }
+            }
+        }
+        matches.sort_by(|a, b| b.0.cmp(&a.0));
+
+        matches
+            .into_iter()
+            .filter_map(|(_, file_id, item_id)| {
+                let data = self.data.get(&file_id)?;
+                let item = data.items.get(item_id)?;
+                let document = self.files.get_by_right(&file_id)?.clone();
+                Some(DocumentRange {
+                    document,
+                    range: self.to_utf16_range(file_id, item.location()),
+                })
+            })
+            .collect()
+    }
+
+    /// Resolves the module name a symbol under `location` refers to,
+    /// whether the cursor sits on the declaration itself or on a use of it.
+    fn resolve_module_name(&self, location: &DocumentPosition) -> Option<String> {
+        let file_id = self.get_fileid(&location.document)?;
+        let position = self.to_byte_position(file_id, &location.position);
+        let (_, semantic) = self.get_item_on_location(file_id, &position)?;
+        match semantic {
+            semantic::Item::ModuleIdentifier { module_name, .. } => Some(module_name.clone()),
+            semantic::Item::ModuleInstance { module_name, .. } => self
+                .data
+                .get(&file_id)
+                .and_then(|data| data.items.get(*module_name))
+                .and_then(|item| item.symbol_name())
+                .map(|name| name.to_string()),
+            semantic::Item::UnknownIdentifier { .. }
+            | semantic::Item::Port { .. }
+            | semantic::Item::Parameter { .. }
+            | semantic::Item::InterfaceIdentifier { .. }
+            | semantic::Item::ProgramIdentifier { .. }
+            | semantic::Item::PackageIdentifier { .. } => None,
+        }
+    }
+
+    /// The inverse of [`Db::goto_definition`]: given a module declaration
+    /// (or any use of it), returns every instantiation site that refers to
+    /// that module across all files.
+    pub fn find_references(&self, location: DocumentPosition) -> Vec<DocumentRange> {
+        let Some(module_name) = self.resolve_module_name(&location) else {
+            return Vec::new();
+        };
+        let Some(sites) = self.references.get(&module_name) else {
+            return Vec::new();
+        };
+
+        sites
+            .iter()
+            .filter_map(|(file_id, item_id)| {
+                let data = self.data.get(file_id)?;
+                let item = data.items.get(*item_id)?;
+                let document = self.files.get_by_right(file_id)?.clone();
+                Some(DocumentRange {
+                    document,
+                    range: self.to_utf16_range(*file_id, item.location()),
+                })
+            })
+            .collect()
     }
 
     pub fn goto_definition(&self, request_location: DocumentPosition) -> Option<DocumentRange> {
         let file_id = self.get_fileid(&request_location.document)?;
-        let semantic = self.get_item_on_location(file_id, &request_location.position)?;
+        let position = self.to_byte_position(file_id, &request_location.position);
+        let (item_id, semantic) = self.get_item_on_location(file_id, &position)?;
         let (file_id, location) = match semantic {
-            semantic::Item::ModuleIdentifier { module_name, .. } => self
-                .get_module(module_name)
-                .map(|(id, item)| (id, item.location())),
+            semantic::Item::ModuleIdentifier { module_name, scope, .. } => self
+                .get_module(scope, module_name)
+                .map(|(id, _, item)| (id, item.location())),
             semantic::Item::ModuleInstance { instance_name, .. } => self
                 .data
                 .get(&file_id)
                 .and_then(|data| data.items.get(*instance_name))
                 .map(|item| (file_id, item.location())),
-            semantic::Item::UnknownIdentifier { location, .. } => Some((file_id, location)),
+            // A port/parameter connection (e.g. `.clk` in an instantiation)
+            // resolves to the declaration in the instantiated module;
+            // anything else just points at itself.
+            semantic::Item::UnknownIdentifier { location, name } => self
+                .resolve_named_connection(file_id, item_id, name)
+                .or(Some((file_id, location))),
+            semantic::Item::Port { location, .. } => Some((file_id, location)),
+            semantic::Item::Parameter { location, .. } => Some((file_id, location)),
+            // Both an interface declaration's own identifier and a use of it
+            // (a `virtual interface` type, lowered in `DataPerFile::new`)
+            // are the same kind of item, so a lookup by name resolves either
+            // one straight back to the declaration. Only program
+            // declarations are lowered, so a program resolves to itself.
+            semantic::Item::InterfaceIdentifier { name, .. } => self
+                .get_namespaced_item("interface", "", name)
+                .map(|(id, _, item)| (id, item.location())),
+            semantic::Item::ProgramIdentifier { name, .. } => self
+                .get_namespaced_item("program", "", name)
+                .map(|(id, _, item)| (id, item.location())),
+            // `pkg::name` isn't resolved to the member `name` (that needs a
+            // scoped-member lowering pass this crate doesn't have yet), so
+            // intentionally out of scope: a package is only reachable by
+            // clicking its own declaration, which just points at itself.
+            semantic::Item::PackageIdentifier { location, .. } => Some((file_id, location)),
         }?;
         let document = self
             .files
@@ -111,7 +401,7 @@ impl Db {
 
         Some(DocumentRange {
             document,
-            range: location.clone(),
+            range: self.to_utf16_range(file_id, location),
         })
     }
 }
@@ -141,7 +431,45 @@ impl HasLocate for InstanceIdentifier {
     }
 }
 
+impl HasLocate for PortIdentifier {
+    fn locate(&self) -> &Locate {
+        self.nodes.0.locate()
+    }
+}
+
+impl HasLocate for ParameterIdentifier {
+    fn locate(&self) -> &Locate {
+        self.nodes.0.locate()
+    }
+}
+
+impl HasLocate for InterfaceIdentifier {
+    fn locate(&self) -> &Locate {
+        self.nodes.0.locate()
+    }
+}
+
+impl HasLocate for ProgramIdentifier {
+    fn locate(&self) -> &Locate {
+        self.nodes.0.locate()
+    }
+}
+
+impl HasLocate for PackageIdentifier {
+    fn locate(&self) -> &Locate {
+        self.nodes.0.locate()
+    }
+}
+
 impl DataPerFile {
+    pub fn verified_at(&self) -> Revision {
+        self.verified_at
+    }
+
+    pub fn changed_at(&self) -> Revision {
+        self.changed_at
+    }
+
     fn get_str<'a>(syntax_tree: &'a SyntaxTree, node: RefNode<'a>) -> Option<&'a str> {
         syntax_tree.get_str_trim(RefNodes(vec![node]))
     }
@@ -172,16 +500,364 @@ impl DataPerFile {
         idx
     }
 
+    /// Finds the `ModuleInstance` (if any) whose port or parameter
+    /// connections list contains `item_id`.
+    fn find_owning_instance(&self, item_id: ItemId) -> Option<&semantic::Item> {
+        self.items.iter().map(|(_, item)| item).find(|item| {
+            matches!(
+                item,
+                semantic::Item::ModuleInstance { ports, parameters, .. }
+                    if ports.contains(&item_id) || parameters.contains(&item_id)
+            )
+        })
+    }
+
+    /// Lowers a module's ANSI port list (`header.nodes.6`), registering a
+    /// `Port` item per declared port so instantiations elsewhere can
+    /// resolve their named connections against it.
+    fn process_ansi_ports(
+        &mut self,
+        syntax_tree: &SyntaxTree,
+        module_id: ItemId,
+        ports: &Option<ListOfPortDeclarations>,
+    ) {
+        let Some(ports) = ports else { return };
+        let Some(list) = &ports.nodes.0.nodes.1 else {
+            return;
+        };
+
+        for port in list {
+            let identifier = match port {
+                AnsiPortDeclaration::Net(decl) => &decl.nodes.3,
+                AnsiPortDeclaration::Variable(decl) => &decl.nodes.3,
+                // `[ port_direction ] . port_identifier ( [ expression ] )`
+                AnsiPortDeclaration::Paren(decl) => &decl.nodes.2,
+            };
+            let name = Self::get_str(syntax_tree, RefNode::PortIdentifier(identifier))
+                .unwrap()
+                .to_string();
+            let location = self.get_location_of_node(identifier);
+            let port_id = self.insert_semantic(semantic::Item::Port {
+                name: name.clone(),
+                module: module_id,
+                location,
+            });
+
+            self.module_ports
+                .entry(module_id)
+                .or_default()
+                .insert(name, port_id);
+        }
+    }
+
+    /// Lowers a module's `#( ... )` parameter port list, registering a
+    /// `Parameter` item per declared parameter so instantiations elsewhere
+    /// can resolve their named `#(.WIDTH(8))` connections against it. Only
+    /// the plain `list_of_param_assignments` form is handled; a list mixing
+    /// in further `parameter_port_declaration`s is left unhandled for now,
+    /// like non-ANSI ports in `lower_module_declaration`.
+    fn process_parameter_port_list(
+        &mut self,
+        syntax_tree: &SyntaxTree,
+        module_id: ItemId,
+        parameters: &Option<ParameterPortList>,
+    ) {
+        let Some(ParameterPortList::Assignment(list)) = parameters else {
+            return;
+        };
+
+        for assignment in &list.nodes.0.nodes.0 {
+            let identifier = &assignment.nodes.0;
+            let name = Self::get_str(syntax_tree, RefNode::ParameterIdentifier(identifier))
+                .unwrap()
+                .to_string();
+            let location = self.get_location_of_node(identifier);
+            let param_id = self.insert_semantic(semantic::Item::Parameter {
+                name: name.clone(),
+                module: module_id,
+                location,
+            });
+
+            self.module_parameters
+                .entry(module_id)
+                .or_default()
+                .insert(name, param_id);
+        }
+    }
+
+    /// Lowers a hierarchical instance's named port connections (e.g.
+    /// `.clk(sys_clk)`) into `UnknownIdentifier` items carrying just the
+    /// connection's location; resolving them against the instantiated
+    /// module's ports happens lazily in `Db::goto_definition`.
+    fn process_port_connections(
+        &mut self,
+        syntax_tree: &SyntaxTree,
+        connections: &Paren<Option<ListOfPortConnections>>,
+    ) -> Vec<ItemId> {
+        let Some(ListOfPortConnections::Named(list)) = &connections.nodes.1 else {
+            return Vec::new();
+        };
+
+        list.into_iter()
+            .filter_map(|connection| match connection {
+                NamedPortConnection::Identifier(connection) => {
+                    let identifier = &connection.nodes.1;
+                    let name = Self::get_str(syntax_tree, RefNode::PortIdentifier(identifier))
+                        .unwrap()
+                        .to_string();
+                    let location = self.get_location_of_node(identifier);
+
+                    Some(self.insert_semantic(semantic::Item::UnknownIdentifier { name, location }))
+                }
+                NamedPortConnection::Wildcard(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lowers a hierarchical instance's `#( ... )` named parameter
+    /// connections (e.g. `#(.WIDTH(8))`) into `UnknownIdentifier` items
+    /// carrying just the connection's location; resolving them against the
+    /// instantiated module's parameters happens lazily in
+    /// `Db::goto_definition`, mirroring `process_port_connections`.
+    fn process_parameter_connections(
+        &mut self,
+        syntax_tree: &SyntaxTree,
+        assignment: &Option<ParameterValueAssignment>,
+    ) -> Vec<ItemId> {
+        let Some(assignment) = assignment else {
+            return Vec::new();
+        };
+        let Some(ListOfParameterAssignments::Named(list)) = &assignment.nodes.1.nodes.1 else {
+            return Vec::new();
+        };
+
+        list.into_iter()
+            .map(|connection| {
+                let identifier = &connection.nodes.1;
+                let name = Self::get_str(syntax_tree, RefNode::ParameterIdentifier(identifier))
+                    .unwrap()
+                    .to_string();
+                let location = self.get_location_of_node(identifier);
+
+                self.insert_semantic(semantic::Item::UnknownIdentifier { name, location })
+            })
+            .collect()
+    }
+
+    /// Lowers one `module_declaration`, registering it (and recursing into
+    /// any nested module declarations) under `scope` — empty for a
+    /// top-level module, or the dot-separated path of enclosing module
+    /// names for one nested inside another, so a nested module gets its own
+    /// scope instead of colliding with a top-level module of the same name.
+    fn lower_module_declaration(
+        &mut self,
+        syntax_tree: &SyntaxTree,
+        module: &ModuleDeclaration,
+        scope: &str,
+    ) {
+        match module {
+            ModuleDeclaration::Nonansi(module) => {
+                let header = &module.nodes.0;
+                let end_locate = module.nodes.4.as_ref().map_or_else(
+                    || &module.nodes.3.nodes.0,
+                    |(symbol, _)| &symbol.nodes.0,
+                );
+
+                let items = module.nodes.2.iter().filter_map(|item| {
+                    if let ModuleItem::NonPortModuleItem(item) = item {
+                        Some(item.as_ref())
+                    } else {
+                        None
+                    }
+                });
+
+                self.process_module_declaration(
+                    syntax_tree,
+                    &header.nodes.1,
+                    &header.nodes.3,
+                    end_locate,
+                    &header.nodes.5,
+                    // Non-ANSI ports are plain names, not the typed
+                    // declarations ANSI headers carry; left unhandled for
+                    // now.
+                    &None,
+                    scope,
+                    items,
+                );
+            }
+            ModuleDeclaration::Ansi(module) => {
+                let header = &module.nodes.0;
+                let end_locate = module.nodes.4.as_ref().map_or_else(
+                    || &module.nodes.3.nodes.0,
+                    |(symbol, _)| &symbol.nodes.0,
+                );
+
+                let items = module.nodes.2.iter();
+
+                self.process_module_declaration(
+                    syntax_tree,
+                    &header.nodes.1,
+                    &header.nodes.3,
+                    end_locate,
+                    &header.nodes.5,
+                    &header.nodes.6,
+                    scope,
+                    items,
+                );
+            }
+            ModuleDeclaration::Wildcard(_) => todo!(),
+            ModuleDeclaration::ExternNonansi(_) => todo!(),
+            ModuleDeclaration::ExternAnsi(_) => todo!(),
+        }
+    }
+
+    /// Lowers a top-level `interface_declaration`, registering it under the
+    /// `"interface"` namespace. Interface bodies aren't lowered yet — this
+    /// only indexes the declaration itself; `virtual interface` references
+    /// to it are lowered separately in `process_interface_reference`.
+    fn process_interface_declaration(&mut self, syntax_tree: &SyntaxTree, decl: &InterfaceDeclaration) {
+        let (identifier, end_locate) = match decl {
+            InterfaceDeclaration::Nonansi(interface) => {
+                let header = &interface.nodes.0;
+                let end_locate = interface.nodes.4.as_ref().map_or_else(
+                    || &interface.nodes.3.nodes.0,
+                    |(symbol, _)| &symbol.nodes.0,
+                );
+                (&header.nodes.3, end_locate)
+            }
+            InterfaceDeclaration::Ansi(interface) => {
+                let header = &interface.nodes.0;
+                let end_locate = interface.nodes.4.as_ref().map_or_else(
+                    || &interface.nodes.3.nodes.0,
+                    |(symbol, _)| &symbol.nodes.0,
+                );
+                (&header.nodes.3, end_locate)
+            }
+            // `extern`/wildcard interface forms have no body to index here.
+            InterfaceDeclaration::Wildcard(_)
+            | InterfaceDeclaration::ExternNonansi(_)
+            | InterfaceDeclaration::ExternAnsi(_) => return,
+        };
+
+        let name = Self::get_str(syntax_tree, RefNode::InterfaceIdentifier(identifier))
+            .unwrap()
+            .to_string();
+        let location = Range {
+            begin: self.line_index.locate_to_position(identifier.locate()),
+            end: self.line_index.locate_to_position(end_locate),
+        };
+        let item_id = self.insert_semantic(semantic::Item::InterfaceIdentifier {
+            name: name.clone(),
+            location,
+        });
+        self.global_items
+            .insert(namespace_key("interface", "", &name), item_id);
+    }
+
+    /// Lowers a top-level `program_declaration`, registering it under the
+    /// `"program"` namespace. Mirrors [`Self::process_interface_declaration`]:
+    /// program bodies aren't lowered yet, only the declaration itself.
+    fn process_program_declaration(&mut self, syntax_tree: &SyntaxTree, decl: &ProgramDeclaration) {
+        let (identifier, end_locate) = match decl {
+            ProgramDeclaration::Nonansi(program) => {
+                let header = &program.nodes.0;
+                let end_locate = program.nodes.4.as_ref().map_or_else(
+                    || &program.nodes.3.nodes.0,
+                    |(symbol, _)| &symbol.nodes.0,
+                );
+                (&header.nodes.3, end_locate)
+            }
+            ProgramDeclaration::Ansi(program) => {
+                let header = &program.nodes.0;
+                let end_locate = program.nodes.4.as_ref().map_or_else(
+                    || &program.nodes.3.nodes.0,
+                    |(symbol, _)| &symbol.nodes.0,
+                );
+                (&header.nodes.3, end_locate)
+            }
+            ProgramDeclaration::Wildcard(_)
+            | ProgramDeclaration::ExternNonansi(_)
+            | ProgramDeclaration::ExternAnsi(_) => return,
+        };
+
+        let name = Self::get_str(syntax_tree, RefNode::ProgramIdentifier(identifier))
+            .unwrap()
+            .to_string();
+        let location = Range {
+            begin: self.line_index.locate_to_position(identifier.locate()),
+            end: self.line_index.locate_to_position(end_locate),
+        };
+        let item_id = self.insert_semantic(semantic::Item::ProgramIdentifier {
+            name: name.clone(),
+            location,
+        });
+        self.global_items
+            .insert(namespace_key("program", "", &name), item_id);
+    }
+
+    /// Lowers a top-level `package_declaration`, registering it under the
+    /// `"package"` namespace. Only the declaration itself is indexed:
+    /// package bodies and `pkg::member` references are not lowered, so a
+    /// `pkg::member` use resolves to nothing.
+    fn process_package_declaration(&mut self, syntax_tree: &SyntaxTree, decl: &PackageDeclaration) {
+        let identifier = &decl.nodes.3;
+        let name = Self::get_str(syntax_tree, RefNode::PackageIdentifier(identifier))
+            .unwrap()
+            .to_string();
+        let location = self.get_location_of_node(identifier);
+        let item_id = self.insert_semantic(semantic::Item::PackageIdentifier {
+            name: name.clone(),
+            location,
+        });
+        self.global_items
+            .insert(namespace_key("package", "", &name), item_id);
+    }
+
+    /// Lowers a use of an interface name outside its own declaration (e.g.
+    /// the `foo_if` in a `virtual interface foo_if vif;` variable
+    /// declaration) as another `InterfaceIdentifier` item, so
+    /// `Db::goto_definition` can land on it the same way it already does
+    /// for `ModuleIdentifier` instantiation references. `for node in
+    /// syntax_tree` walks every node in the file, including the
+    /// declaration's own identifier, so anything already indexed at this
+    /// location (the declaration itself) is skipped.
+    fn process_interface_reference(&mut self, syntax_tree: &SyntaxTree, identifier: &InterfaceIdentifier) {
+        let location = self.get_location_of_node(identifier);
+        if self
+            .location_map
+            .binary_search_by(|(l, _)| l.begin.cmp(&location.begin))
+            .is_ok()
+        {
+            return;
+        }
+
+        let name = Self::get_str(syntax_tree, RefNode::InterfaceIdentifier(identifier))
+            .unwrap()
+            .to_string();
+        self.insert_semantic(semantic::Item::InterfaceIdentifier { name, location });
+    }
+
     fn process_module_declaration<'a, ITEM: Iterator<Item = &'a NonPortModuleItem>>(
         &mut self,
         syntax_tree: &SyntaxTree,
         module_keyword: &ModuleKeyword,
         identifier: &ModuleIdentifier,
         end_locate: &Locate,
+        parameters: &Option<ParameterPortList>,
+        ports: &Option<ListOfPortDeclarations>,
+        scope: &str,
         items: ITEM,
     ) {
         let module_name =
             Self::get_str(&syntax_tree, RefNode::ModuleIdentifier(identifier)).unwrap();
+        // The scope this module itself registers nested declarations and
+        // instantiations under — its own dot-separated path, used as the
+        // starting point for any lookup made from within its body.
+        let self_scope = if scope.is_empty() {
+            module_name.to_string()
+        } else {
+            format!("{scope}.{module_name}")
+        };
 
         let locate = match module_keyword {
             ModuleKeyword::Module(keyword) => &keyword.nodes.0,
@@ -194,12 +870,19 @@ impl DataPerFile {
 
         let module_id = self.insert_semantic(semantic::Item::ModuleIdentifier {
             module_name: module_name.to_string(),
+            scope: scope.to_string(),
             location: location,
         });
 
+        self.process_parameter_port_list(syntax_tree, module_id, parameters);
+        self.process_ansi_ports(syntax_tree, module_id, ports);
+
         for item in items {
             match item {
-                NonPortModuleItem::GenerateRegion(_) => todo!(),
+                // Generate-scoped declarations aren't indexed yet, but a
+                // generate region is otherwise ordinary module content, not
+                // an error, so skip it gracefully rather than panicking.
+                NonPortModuleItem::GenerateRegion(_) => {}
                 NonPortModuleItem::ModuleOrGenerateItem(item) => match item.as_ref() {
                     ModuleOrGenerateItem::Parameter(_) => todo!(),
                     ModuleOrGenerateItem::Gate(_) => todo!(),
@@ -213,9 +896,11 @@ impl DataPerFile {
                                 .to_string();
                         let location = self.get_location_of_node(identifier);
                         let module_id = self.insert_semantic(semantic::Item::ModuleIdentifier {
-                            module_name,
+                            module_name: module_name.clone(),
+                            scope: self_scope.clone(),
                             location,
                         });
+                        self.references.entry(module_name).or_default().push(module_id);
 
                         let instance_name_node = &item.nodes.2.nodes.0.nodes.0.nodes.0;
                         let instance_name = Self::get_str(
@@ -232,12 +917,16 @@ impl DataPerFile {
                             });
                         let location =
                             self.get_location_of_node(&item.nodes.2.nodes.0.nodes.0.nodes.0);
+                        let ports = self
+                            .process_port_connections(syntax_tree, &item.nodes.2.nodes.0.nodes.1);
+                        let parameters =
+                            self.process_parameter_connections(syntax_tree, &item.nodes.1);
 
                         self.items.insert(semantic::Item::ModuleInstance {
                             module_name: module_id,
                             instance_name,
-                            parameters: Vec::new(),
-                            ports: Vec::new(),
+                            parameters,
+                            ports,
                             location: location.clone(),
                         });
                     }
@@ -245,14 +934,19 @@ impl DataPerFile {
                 },
                 NonPortModuleItem::SpecifyBlock(_) => todo!(),
                 NonPortModuleItem::Specparam(_) => todo!(),
-                NonPortModuleItem::ProgramDeclaration(_) => todo!(),
-                NonPortModuleItem::ModuleDeclaration(_) => todo!(),
-                NonPortModuleItem::InterfaceDeclaration(_) => todo!(),
+                // Nested program/interface declarations aren't indexed yet;
+                // acknowledge and skip rather than panicking.
+                NonPortModuleItem::ProgramDeclaration(_) => {}
+                NonPortModuleItem::ModuleDeclaration(nested) => {
+                    self.lower_module_declaration(syntax_tree, nested.as_ref(), &self_scope);
+                }
+                NonPortModuleItem::InterfaceDeclaration(_) => {}
                 NonPortModuleItem::TimeunitsDeclaration(_) => todo!(),
             }
         }
 
-        self.global_items.insert(module_name.to_string(), module_id);
+        self.global_items
+            .insert(namespace_key("module", scope, module_name), module_id);
     }
 
     pub fn new(syntax_tree: &SyntaxTree) -> Self {
@@ -262,6 +956,13 @@ impl DataPerFile {
             items: Arena::new(),
             location_map: Vec::new(),
             global_items: HashMap::new(),
+            symbol_index: FileSymbolIndex::default(),
+            references: HashMap::new(),
+            module_ports: HashMap::new(),
+            module_parameters: HashMap::new(),
+            fingerprint: 0,
+            verified_at: Revision::default(),
+            changed_at: Revision::default(),
             line_index,
         };
         for node in syntax_tree {
@@ -270,57 +971,34 @@ impl DataPerFile {
                     eprintln!("{:#?}", source_text);
                     for desc in &source_text.nodes.2 {
                         match desc {
-                            Description::ModuleDeclaration(module) => match module.as_ref() {
-                                ModuleDeclaration::Nonansi(module) => {
-                                    let header = &module.nodes.0;
-                                    let end_locate = module.nodes.4.as_ref().map_or_else(
-                                        || &module.nodes.3.nodes.0,
-                                        |(symbol, _)| &symbol.nodes.0,
-                                    );
-
-                                    // header.nodes.6.into_iter().map(|a| a);
-
-                                    let items = module.nodes.2.iter().filter_map(|item| {
-                                        if let ModuleItem::NonPortModuleItem(item) = item {
-                                            Some(item.as_ref())
-                                        } else {
-                                            None
-                                        }
-                                    });
-
-                                    ret.process_module_declaration(
-                                        &syntax_tree,
-                                        &header.nodes.1,
-                                        &header.nodes.3,
-                                        end_locate,
-                                        items,
-                                    );
-                                }
-                                ModuleDeclaration::Ansi(module) => {
-                                    let header = &module.nodes.0;
-                                    let end_locate = module.nodes.4.as_ref().map_or_else(
-                                        || &module.nodes.3.nodes.0,
-                                        |(symbol, _)| &symbol.nodes.0,
-                                    );
-
-                                    let items = module.nodes.2.iter();
-
-                                    ret.process_module_declaration(
-                                        &syntax_tree,
-                                        &header.nodes.1,
-                                        &header.nodes.3,
-                                        end_locate,
-                                        items,
-                                    );
-                                }
-                                ModuleDeclaration::Wildcard(_) => todo!(),
-                                ModuleDeclaration::ExternNonansi(_) => todo!(),
-                                ModuleDeclaration::ExternAnsi(_) => todo!(),
-                            },
+                            Description::ModuleDeclaration(module) => {
+                                ret.lower_module_declaration(&syntax_tree, module.as_ref(), "");
+                            }
+                            Description::InterfaceDeclaration(decl) => {
+                                ret.process_interface_declaration(&syntax_tree, decl.as_ref());
+                            }
+                            Description::ProgramDeclaration(decl) => {
+                                ret.process_program_declaration(&syntax_tree, decl.as_ref());
+                            }
+                            Description::PackageDeclaration(decl) => {
+                                ret.process_package_declaration(&syntax_tree, decl.as_ref());
+                            }
                             _ => { /* not yet */ }
                         }
                     }
                 }
+                // A `virtual interface` typed declaration referencing an
+                // interface elsewhere; the declaration's own identifier is
+                // also an `InterfaceIdentifier` node, so skip anything
+                // that's already indexed at this location.
+                RefNode::InterfaceIdentifier(identifier) => {
+                    ret.process_interface_reference(&syntax_tree, identifier);
+                }
+                // `pkg::name` isn't lowered here: resolving it to the
+                // package declaration alone (and not the actual member
+                // `name`) would be a worse goto_definition than doing
+                // nothing, so it's left out of scope rather than wired in
+                // half-right. See `goto_definition`'s `PackageIdentifier` arm.
                 _ => { /* do nothing */ }
             };
         }
@@ -329,6 +1007,55 @@ impl DataPerFile {
             eprintln!("{:?} - {:?}", location, ret.items.get(*syntax));
         }
 
+        ret.symbol_index = FileSymbolIndex::new(&ret.items);
+
         ret
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn parse(src: &str) -> SyntaxTree {
+        sv_parser::parse_sv_str(
+            src,
+            PathBuf::from("test.sv"),
+            &HashMap::new(),
+            &Vec::<PathBuf>::new(),
+            false,
+            false,
+        )
+        .unwrap()
+        .0
+    }
+
+    #[test]
+    fn reupdating_identical_source_does_not_recompute() {
+        let mut db = Db::default();
+        let tree = parse("module m; endmodule\n");
+        let file_id = db.update(PathBuf::from("m.sv"), &tree);
+        let changed_at = db.get_data(file_id).unwrap().changed_at();
+
+        let file_id_again = db.update(PathBuf::from("m.sv"), &tree);
+
+        assert_eq!(file_id, file_id_again);
+        let data = db.get_data(file_id).unwrap();
+        assert_eq!(data.changed_at(), changed_at);
+        assert!(data.verified_at() > changed_at);
+    }
+
+    #[test]
+    fn updating_with_changed_source_recomputes() {
+        let mut db = Db::default();
+        let tree_a = parse("module m; endmodule\n");
+        let file_id = db.update(PathBuf::from("m.sv"), &tree_a);
+        let changed_at = db.get_data(file_id).unwrap().changed_at();
+
+        let tree_b = parse("module mm; endmodule\n");
+        db.update(PathBuf::from("m.sv"), &tree_b);
+
+        assert!(db.get_data(file_id).unwrap().changed_at() > changed_at);
+    }
+}
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use sv_parser::{Locate, RefNode, SyntaxTree, WhiteSpace};
+use sv_parser::{Locate, RefNode, RefNodes, SyntaxTree, WhiteSpace};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Position {
@@ -60,7 +60,25 @@ impl PartialOrd<Position> for Range {
     }
 }
 
-pub struct LineIndex(Vec<usize>);
+/// A non-ASCII character on some line, recorded so columns can be converted
+/// between byte and UTF-16 offsets without rescanning the source text.
+#[derive(Debug, Clone, Copy)]
+struct WideChar {
+    /// Byte offset of this character from the start of its line.
+    byte_offset: u32,
+    /// UTF-16 offset of this character from the start of its line.
+    utf16_offset: u32,
+    utf8_len: u32,
+    utf16_len: u32,
+}
+
+pub struct LineIndex {
+    /// Sorted byte offsets of every line start.
+    line_offsets: Vec<usize>,
+    /// Per line (indexed the same as `line_offsets`), every non-ASCII
+    /// character on that line, in order.
+    wide_chars: Vec<Vec<WideChar>>,
+}
 
 impl LineIndex {
     pub fn new(syntax_tree: &SyntaxTree) -> Self {
@@ -88,35 +106,201 @@ impl LineIndex {
             }
         }
 
-        Self(offsets)
+        let mut wide_chars = vec![Vec::new(); offsets.len()];
+        if let Some(text) = Self::full_text(syntax_tree) {
+            let mut line = 0;
+            // Cumulative (utf8_len - utf16_len) of every wide char already
+            // seen on the current line, used to translate a byte column
+            // into a UTF-16 column as we go.
+            let mut line_delta: i64 = 0;
+
+            for (byte_offset, ch) in text.char_indices() {
+                while line + 1 < offsets.len() && offsets[line + 1] <= byte_offset {
+                    line += 1;
+                    line_delta = 0;
+                }
+                if ch.is_ascii() {
+                    continue;
+                }
+
+                let utf8_len = ch.len_utf8() as u32;
+                let utf16_len = ch.len_utf16() as u32;
+                let byte_col = (byte_offset - offsets[line]) as u32;
+                let utf16_col = (byte_col as i64 - line_delta) as u32;
+
+                wide_chars[line].push(WideChar {
+                    byte_offset: byte_col,
+                    utf16_offset: utf16_col,
+                    utf8_len,
+                    utf16_len,
+                });
+                line_delta += utf8_len as i64 - utf16_len as i64;
+            }
+        }
+
+        Self {
+            line_offsets: offsets,
+            wide_chars,
+        }
+    }
+
+    fn full_text(syntax_tree: &SyntaxTree) -> Option<&str> {
+        for node in syntax_tree {
+            if let RefNode::SourceText(_) = node {
+                return syntax_tree.get_str(RefNodes(vec![node]));
+            }
+        }
+        None
     }
 
     pub fn locate_to_position(&self, locate: &Locate) -> Position {
-        let ret =
-            Position {
-                row: locate.line - 1,
-                col: (locate.offset
-                    - self.0.get(locate.line as usize - 1).unwrap_or_else(|| {
-                        panic!("line_index mismatched at line: {}", locate.line)
-                    })) as _,
-            };
-        ret
+        self.offset_to_position(locate.offset)
     }
 
     pub fn offset_to_position(&self, offset: usize) -> Position {
-        let mut col = 0;
-        let mut line = 0;
-        for (idx, accumulated) in self.0.iter().enumerate() {
-            line = idx;
-            if *accumulated > offset {
-                break;
-            }
-            col = offset - accumulated;
+        let line = self.line_offsets.partition_point(|&o| o <= offset) - 1;
+        let col = (offset - self.line_offsets[line]) as u32;
+
+        Position {
+            row: line as u32,
+            col,
+        }
+    }
+
+    /// Converts a byte-column `Position` (as produced by
+    /// [`LineIndex::offset_to_position`]) into the UTF-16 code-unit columns
+    /// LSP clients expect.
+    pub fn to_utf16(&self, pos: Position) -> Position {
+        let Some(chars) = self.wide_chars.get(pos.row as usize) else {
+            return pos;
+        };
+
+        let delta: i64 = chars
+            .iter()
+            .take_while(|c| c.byte_offset < pos.col)
+            .map(|c| c.utf16_len as i64 - c.utf8_len as i64)
+            .sum();
+
+        Position {
+            row: pos.row,
+            col: (pos.col as i64 + delta) as u32,
         }
+    }
+
+    /// The inverse of [`LineIndex::to_utf16`]: converts a UTF-16 column back
+    /// into a byte column.
+    pub fn from_utf16(&self, pos: Position) -> Position {
+        let Some(chars) = self.wide_chars.get(pos.row as usize) else {
+            return pos;
+        };
+
+        let delta: i64 = chars
+            .iter()
+            .take_while(|c| c.utf16_offset < pos.col)
+            .map(|c| c.utf8_len as i64 - c.utf16_len as i64)
+            .sum();
 
         Position {
-            row: (line - 1) as _,
-            col: col as _,
+            row: pos.row,
+            col: (pos.col as i64 + delta) as u32,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `LineIndex::new` itself needs a real `SyntaxTree` to walk, which means
+    // a parsed file — these tests build `LineIndex` directly from its
+    // (private) parts instead, to exercise the byte<->UTF-16 column math in
+    // isolation from `sv_parser`.
+
+    #[test]
+    fn ascii_only_line_is_unaffected_by_conversion() {
+        let index = LineIndex {
+            line_offsets: vec![0],
+            wide_chars: vec![Vec::new()],
+        };
+        let pos = Position { row: 0, col: 5 };
+        assert_eq!(index.to_utf16(pos.clone()), pos.clone());
+        assert_eq!(index.from_utf16(pos.clone()), pos);
+    }
+
+    #[test]
+    fn two_byte_char_shifts_later_columns_back_in_utf16() {
+        // Line: "é=1;" — `é` is 2 UTF-8 bytes but 1 UTF-16 unit, at byte
+        // column 0.
+        let index = LineIndex {
+            line_offsets: vec![0],
+            wide_chars: vec![vec![WideChar {
+                byte_offset: 0,
+                utf16_offset: 0,
+                utf8_len: 2,
+                utf16_len: 1,
+            }]],
+        };
+        // Byte column 5 is past `é`, which is 1 byte wider in UTF-8 than
+        // UTF-16, so the UTF-16 column is 1 less.
+        let utf16 = index.to_utf16(Position { row: 0, col: 5 });
+        assert_eq!(utf16, Position { row: 0, col: 4 });
+        assert_eq!(index.from_utf16(utf16), Position { row: 0, col: 5 });
+    }
+
+    #[test]
+    fn surrogate_pair_char_counts_as_two_utf16_units() {
+        // Line: "😀!" — an astral character is 4 UTF-8 bytes but a UTF-16
+        // surrogate pair (2 code units), at byte column 0.
+        let index = LineIndex {
+            line_offsets: vec![0],
+            wide_chars: vec![vec![WideChar {
+                byte_offset: 0,
+                utf16_offset: 0,
+                utf8_len: 4,
+                utf16_len: 2,
+            }]],
+        };
+        let utf16 = index.to_utf16(Position { row: 0, col: 4 });
+        assert_eq!(utf16, Position { row: 0, col: 2 });
+        assert_eq!(index.from_utf16(utf16), Position { row: 0, col: 4 });
+    }
+
+    #[test]
+    fn column_before_any_wide_char_is_unaffected() {
+        let index = LineIndex {
+            line_offsets: vec![0],
+            wide_chars: vec![vec![WideChar {
+                byte_offset: 3,
+                utf16_offset: 3,
+                utf8_len: 2,
+                utf16_len: 1,
+            }]],
+        };
+        let pos = Position { row: 0, col: 1 };
+        assert_eq!(index.to_utf16(pos.clone()), pos.clone());
+        assert_eq!(index.from_utf16(pos), pos.clone());
+    }
+
+    #[test]
+    fn missing_line_falls_back_to_the_original_position() {
+        let index = LineIndex {
+            line_offsets: vec![0],
+            wide_chars: vec![Vec::new()],
+        };
+        let pos = Position { row: 4, col: 2 };
+        assert_eq!(index.to_utf16(pos.clone()), pos.clone());
+        assert_eq!(index.from_utf16(pos.clone()), pos);
+    }
+
+    #[test]
+    fn offset_to_position_finds_the_right_line() {
+        // Three lines starting at bytes 0, 10 and 15.
+        let index = LineIndex {
+            line_offsets: vec![0, 10, 15],
+            wide_chars: vec![Vec::new(), Vec::new(), Vec::new()],
+        };
+        assert_eq!(index.offset_to_position(0), Position { row: 0, col: 0 });
+        assert_eq!(index.offset_to_position(12), Position { row: 1, col: 2 });
+        assert_eq!(index.offset_to_position(15), Position { row: 2, col: 0 });
+    }
+}
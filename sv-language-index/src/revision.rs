@@ -0,0 +1,76 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    mem::discriminant,
+};
+
+use sv_parser::{unwrap_locate, SyntaxTree};
+
+/// Monotonically increasing counter bumped once per [`crate::Db::update`]
+/// call. Derived per-file data records the revision it was last verified at
+/// (`verified_at`) and the revision it actually changed at (`changed_at`),
+/// so a no-op edit can be recognized without recomputing anything
+/// downstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Revision(u64);
+
+impl Revision {
+    pub fn next(self) -> Self {
+        Revision(self.0 + 1)
+    }
+}
+
+/// A cheap fingerprint of a syntax tree's contents, used to detect whether a
+/// freshly parsed tree actually differs from the one already cached for a
+/// file before paying for re-lowering and re-indexing.
+///
+/// `for node in syntax_tree` already visits every node exactly once, so each
+/// node only contributes its own discriminant (which grammar rule/variant it
+/// is) plus the offset, length and source text of the first `Locate` at or
+/// beneath it (as found by `unwrap_locate!`) — never the `Debug` output of
+/// the whole node, which would re-hash everything beneath it that the loop
+/// is about to visit separately anyway.
+pub fn fingerprint(syntax_tree: &SyntaxTree) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for node in syntax_tree {
+        discriminant(&node).hash(&mut hasher);
+        if let Some(locate) = unwrap_locate!(node) {
+            locate.offset.hash(&mut hasher);
+            locate.len.hash(&mut hasher);
+            syntax_tree.get_str(locate).hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashMap, path::PathBuf};
+
+    fn parse(src: &str) -> SyntaxTree {
+        sv_parser::parse_sv_str(
+            src,
+            PathBuf::from("test.sv"),
+            &HashMap::new(),
+            &Vec::<PathBuf>::new(),
+            false,
+            false,
+        )
+        .unwrap()
+        .0
+    }
+
+    #[test]
+    fn reparsing_identical_source_gives_an_identical_fingerprint() {
+        let src = "module m; endmodule\n";
+        assert_eq!(fingerprint(&parse(src)), fingerprint(&parse(src)));
+    }
+
+    #[test]
+    fn a_single_character_edit_changes_the_fingerprint() {
+        let a = parse("module m; endmodule\n");
+        let b = parse("module mm; endmodule\n");
+        assert_ne!(fingerprint(&a), fingerprint(&b));
+    }
+}
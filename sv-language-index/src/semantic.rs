@@ -4,6 +4,13 @@ use crate::{ItemId, Range};
 pub enum Item {
     ModuleIdentifier {
         module_name: String,
+        /// The dot-separated path of enclosing module names this
+        /// identifier is lexically nested under (empty at the top level).
+        /// For a declaration this is its own registered scope; for an
+        /// instantiation reference it's the scope of the module doing the
+        /// instantiating — i.e. where an unscoped lookup for `module_name`
+        /// should start before falling back through enclosing scopes.
+        scope: String,
         location: Range,
     },
     ModuleInstance {
@@ -17,6 +24,30 @@ pub enum Item {
         name: String,
         location: Range,
     },
+    /// A port declared in a module's ANSI header, e.g. `clk` in
+    /// `module m(input clk);`.
+    Port {
+        name: String,
+        module: ItemId,
+        location: Range,
+    },
+    /// A parameter declared in a module's parameter port list.
+    Parameter {
+        name: String,
+        module: ItemId,
+        location: Range,
+    },
+    /// A top-level `interface_declaration`, or a use of that interface's
+    /// name elsewhere (e.g. a `virtual interface` type). Declarations are
+    /// registered in the `"interface"` namespace so they can't collide with
+    /// a module of the same name.
+    InterfaceIdentifier { name: String, location: Range },
+    /// A top-level `program_declaration`. Registered in the `"program"`
+    /// namespace so it can't collide with a module of the same name.
+    ProgramIdentifier { name: String, location: Range },
+    /// A top-level `package_declaration`. Registered in the `"package"`
+    /// namespace so it can't collide with a module of the same name.
+    PackageIdentifier { name: String, location: Range },
 }
 
 impl Item {
@@ -25,6 +56,27 @@ impl Item {
             Item::ModuleIdentifier { location, .. } => location,
             Item::ModuleInstance { location, .. } => location,
             Item::UnknownIdentifier { location, .. } => location,
+            Item::Port { location, .. } => location,
+            Item::Parameter { location, .. } => location,
+            Item::InterfaceIdentifier { location, .. } => location,
+            Item::ProgramIdentifier { location, .. } => location,
+            Item::PackageIdentifier { location, .. } => location,
+        }
+    }
+
+    /// The name this item should be indexed under for symbol search, if any.
+    /// `ModuleInstance` itself has no name; its instance name is indexed
+    /// separately as the `UnknownIdentifier` it points to.
+    pub fn symbol_name(&self) -> Option<&str> {
+        match self {
+            Item::ModuleIdentifier { module_name, .. } => Some(module_name),
+            Item::UnknownIdentifier { name, .. } => Some(name),
+            Item::Port { name, .. } => Some(name),
+            Item::Parameter { name, .. } => Some(name),
+            Item::InterfaceIdentifier { name, .. } => Some(name),
+            Item::ProgramIdentifier { name, .. } => Some(name),
+            Item::PackageIdentifier { name, .. } => Some(name),
+            Item::ModuleInstance { .. } => None,
         }
     }
 }
@@ -0,0 +1,108 @@
+use generational_arena::Arena;
+
+use crate::{semantic, ItemId};
+
+/// One file's worth of indexable symbols, sorted by name so callers can binary
+/// search a prefix or merge several files' entries cheaply.
+#[derive(Default)]
+pub struct FileSymbolIndex(Vec<SymbolEntry>);
+
+pub struct SymbolEntry {
+    pub name: String,
+    pub item: ItemId,
+}
+
+impl FileSymbolIndex {
+    pub fn new(items: &Arena<semantic::Item>) -> Self {
+        let mut entries: Vec<_> = items
+            .iter()
+            .filter_map(|(id, item)| {
+                item.symbol_name().map(|name| SymbolEntry {
+                    name: name.to_string(),
+                    item: id,
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self(entries)
+    }
+
+    pub fn entries(&self) -> &[SymbolEntry] {
+        &self.0
+    }
+}
+
+/// Scores `text` against `query` as a case-insensitive, in-order subsequence
+/// match (e.g. `mInst` matches `my_instance`). Returns `None` when `query`
+/// isn't a subsequence of `text`. Higher scores are better; prefix and
+/// contiguous runs of matched characters are ranked above scattered ones.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let query_chars: Vec<char> = query.chars().collect();
+    let mut query_idx = 0;
+    let mut last_match_idx = None;
+    let mut score = 0i32;
+
+    for (text_idx, text_char) in text.chars().enumerate() {
+        if query_idx >= query_chars.len() {
+            break;
+        }
+        let query_char = query_chars[query_idx];
+        if query_char.eq_ignore_ascii_case(&text_char) {
+            score += if query_char == text_char { 2 } else { 1 };
+            if text_idx == 0 {
+                score += 5;
+            }
+            if last_match_idx == Some(text_idx.wrapping_sub(1)) {
+                score += 3;
+            }
+            last_match_idx = Some(text_idx);
+            query_idx += 1;
+        }
+    }
+
+    (query_idx == query_chars.len()).then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_query_matches_anything() {
+        assert_eq!(fuzzy_match("", "my_instance"), Some(0));
+    }
+
+    #[test]
+    fn non_subsequence_does_not_match() {
+        assert_eq!(fuzzy_match("xyz", "my_instance"), None);
+    }
+
+    #[test]
+    fn subsequence_matches_case_insensitively() {
+        assert!(fuzzy_match("mInst", "my_instance").is_some());
+    }
+
+    #[test]
+    fn out_of_order_characters_do_not_match() {
+        assert_eq!(fuzzy_match("tsni", "my_instance"), None);
+    }
+
+    #[test]
+    fn prefix_and_contiguous_matches_score_higher_than_scattered() {
+        let prefix = fuzzy_match("my", "my_instance").unwrap();
+        let scattered = fuzzy_match("mn", "my_instance").unwrap();
+        assert!(prefix > scattered);
+    }
+
+    #[test]
+    fn exact_case_match_scores_higher_than_case_insensitive_match() {
+        let exact = fuzzy_match("My", "My_instance").unwrap();
+        let insensitive = fuzzy_match("my", "My_instance").unwrap();
+        assert!(exact > insensitive);
+    }
+}